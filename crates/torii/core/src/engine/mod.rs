@@ -0,0 +1,179 @@
+//! Drives block ingestion: pulls blocks from the node over HTTP, runs every
+//! registered processor over their contents, and advances the stored cursor
+//! in the same transaction as the writes they produced.
+//!
+//! [`subscription`] is the push-based alternative to [`Engine::sync_to_head`]'s
+//! polling loop; both ultimately drive the same processor pipeline through
+//! [`Sql::begin_block`]/[`Sql::commit_block`]/[`Sql::rollback_block`].
+
+pub mod subscription;
+
+use anyhow::{Error, Result};
+use dojo_world::contracts::world::WorldContractReader;
+use sqlx::Database;
+use starknet::core::types::{
+    BlockId, BlockWithTxs, Event, InvokeTransactionReceipt, MaybePendingBlockWithTxs,
+    TransactionReceipt,
+};
+use starknet::core::utils::get_selector_from_name;
+use starknet::providers::Provider;
+use tokio::sync::broadcast::Sender;
+
+use crate::processors::{BlockProcessor, EventProcessor, TransactionProcessor};
+use crate::sql::{Placeholder, Sql};
+
+/// Tunables for [`Engine::sync_to_head`].
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// Blocks processed before yielding control back to the runtime, so a
+    /// long initial sync doesn't starve other tasks sharing it.
+    pub blocks_chunk_size: u64,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self { blocks_chunk_size: 10_000 }
+    }
+}
+
+/// The processors [`Engine`] dispatches to, grouped the same way it walks a
+/// block: once per block, once per transaction, and once per event whose
+/// selector matches [`EventProcessor::event_key`].
+pub struct Processors<P: Provider + Sync, Db: Placeholder> {
+    pub block: Vec<Box<dyn BlockProcessor<P, Db> + Send + Sync>>,
+    pub transaction: Vec<Box<dyn TransactionProcessor<P, Db> + Send + Sync>>,
+    pub event: Vec<Box<dyn EventProcessor<P, Db> + Send + Sync>>,
+}
+
+impl<P: Provider + Sync, Db: Placeholder> Default for Processors<P, Db> {
+    fn default() -> Self {
+        Self { block: Vec::new(), transaction: Vec::new(), event: Vec::new() }
+    }
+}
+
+/// Drives ingestion by polling the node over HTTP for new blocks and running
+/// every registered processor over each one inside a single atomic
+/// transaction — see the contract documented on
+/// [`crate::processors::EventProcessor`].
+pub struct Engine<'a, P: Provider + Sync, Db: Placeholder> {
+    world: WorldContractReader<P>,
+    db: &'a mut Sql<Db>,
+    provider: &'a P,
+    processors: Processors<P, Db>,
+    config: EngineConfig,
+    /// Sent the block number of every block as it's committed, so a
+    /// push-based consumer (e.g. a GraphQL subscription) doesn't have to poll
+    /// `Sql` for the head. Dropped notifications (no receivers) are fine —
+    /// the stored cursor remains the source of truth.
+    block_tx: Option<Sender<u64>>,
+}
+
+impl<'a, P, Db> Engine<'a, P, Db>
+where
+    P: Provider + Sync,
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        world: WorldContractReader<P>,
+        db: &'a mut Sql<Db>,
+        provider: &'a P,
+        processors: Processors<P, Db>,
+        config: EngineConfig,
+        block_tx: Option<Sender<u64>>,
+    ) -> Self {
+        Self { world, db, provider, processors, config, block_tx }
+    }
+
+    /// Ingest every block from `from_block` up to the node's current head, one
+    /// at a time: open the block's transaction, run every processor over it,
+    /// advance the stored cursor inside that same transaction, and commit —
+    /// or roll back the whole block on the first processor error, so a
+    /// failing block never lands partial state. Returns the head block number
+    /// reached.
+    pub async fn sync_to_head(&mut self, from_block: u64) -> Result<u64> {
+        let latest = self.provider.block_number().await.map_err(Error::from)?;
+
+        let mut block_number = from_block;
+        while block_number <= latest {
+            self.sync_block(block_number).await?;
+            block_number += 1;
+
+            if (block_number - from_block) % self.config.blocks_chunk_size == 0 {
+                tokio::task::yield_now().await;
+            }
+        }
+        Ok(latest)
+    }
+
+    async fn sync_block(&mut self, block_number: u64) -> Result<()> {
+        let block = self.fetch_block(block_number).await?;
+
+        self.db.begin_block().await?;
+        if let Err(err) = self.process_block(&block).await {
+            self.db.rollback_block().await?;
+            return Err(err);
+        }
+        self.db.set_head(block_number).await?;
+        self.db.commit_block().await?;
+
+        if let Some(block_tx) = &self.block_tx {
+            // No subscribers is not an error: the stored cursor is always the
+            // source of truth, this is purely a low-latency nudge.
+            let _ = block_tx.send(block_number);
+        }
+        Ok(())
+    }
+
+    async fn fetch_block(&self, block_number: u64) -> Result<BlockWithTxs> {
+        match self.provider.get_block_with_txs(BlockId::Number(block_number)).await.map_err(Error::from)? {
+            MaybePendingBlockWithTxs::Block(block) => Ok(block),
+            MaybePendingBlockWithTxs::PendingBlock(_) => {
+                Err(anyhow::anyhow!("block {block_number} is still pending, not ready to ingest"))
+            }
+        }
+    }
+
+    async fn process_block(&mut self, block: &BlockWithTxs) -> Result<()> {
+        for processor in &self.processors.block {
+            processor.process(self.db, self.provider, block).await?;
+        }
+
+        for transaction in &block.transactions {
+            let receipt = self
+                .provider
+                .get_transaction_receipt(transaction.transaction_hash())
+                .await
+                .map_err(Error::from)?;
+
+            for processor in &self.processors.transaction {
+                processor.process(self.db, self.provider, block, &receipt).await?;
+            }
+
+            let TransactionReceipt::Invoke(invoke_receipt) = &receipt else { continue };
+            for event in &invoke_receipt.events {
+                self.process_event(block, invoke_receipt, event).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn process_event(
+        &mut self,
+        block: &BlockWithTxs,
+        invoke_receipt: &InvokeTransactionReceipt,
+        event: &Event,
+    ) -> Result<()> {
+        let Some(&selector) = event.keys.first() else { return Ok(()) };
+        let event_id = format!("{:#x}", invoke_receipt.transaction_hash);
+
+        for processor in &self.processors.event {
+            if get_selector_from_name(&processor.event_key())? == selector {
+                processor.process(&self.world, self.db, block, invoke_receipt, &event_id, event).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod engine;
+pub mod merkle;
+pub mod processors;
+pub mod sql;
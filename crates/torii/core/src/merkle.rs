@@ -0,0 +1,176 @@
+//! Sparse Merkle tree over indexed entities.
+//!
+//! Maintains a fixed-depth Merkle tree whose leaf at index `entity_key` holds
+//! `hash(serialized_entity_record)`, so a light client can verify an entity's
+//! value against a single root without trusting the indexer. Nodes are persisted
+//! in a `merkle_nodes(level, index) -> hash` table; empty subtrees are never
+//! stored and are represented implicitly by [`ZERO_HASHES`].
+//!
+//! The hash is Poseidon over felts — the same primitive Starknet uses for its
+//! storage commitments — so a root computed here can be compared against a root
+//! committed on-chain.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use sqlx::Database;
+use starknet::core::types::FieldElement;
+use starknet_crypto::poseidon_hash;
+
+use crate::sql::{Placeholder, Sql};
+
+/// Tree depth: the entity key is a felt, whose meaningful range is 251 bits.
+pub const DEPTH: usize = 251;
+
+/// `ZERO_HASHES[l]` is the root of an all-empty subtree of height `l`.
+///
+/// A present leaf is a single felt, `hash(serialized_entity_record)`; an
+/// absent leaf has to be a value of the same arity, so `ZERO_HASHES[0]` is
+/// plain [`FieldElement::ZERO`] rather than a hash of anything — hashing a
+/// pair of zeros here would give the empty leaf a different shape than a real
+/// one and make it indistinguishable from a record that legitimately hashed
+/// to `poseidon_hash(ZERO, ZERO)`. From level 1 up, every node combines two
+/// children with [`poseidon_hash`], so each level hashes the previous level's
+/// zero with itself. Precomputing the whole column lets empty siblings be
+/// supplied without reading (or storing) a single zero node.
+pub static ZERO_HASHES: Lazy<[FieldElement; DEPTH + 1]> = Lazy::new(|| {
+    let mut hashes = [FieldElement::ZERO; DEPTH + 1];
+    for level in 1..=DEPTH {
+        hashes[level] = poseidon_hash(hashes[level - 1], hashes[level - 1]);
+    }
+    hashes
+});
+
+/// Persistence backend for tree nodes, keyed by `(level, index)`.
+///
+/// `level` is measured from the leaves (`0`) up to the root (`DEPTH`). A missing
+/// node is an empty subtree and resolves to `ZERO_HASHES[level]`.
+#[async_trait]
+pub trait MerkleStore {
+    async fn get_node(&self, level: usize, index: &FieldElement) -> Result<Option<FieldElement>>;
+    async fn set_node(
+        &mut self,
+        level: usize,
+        index: &FieldElement,
+        hash: FieldElement,
+    ) -> Result<()>;
+}
+
+/// A Merkle proof: the current root and the ordered sibling hashes from the
+/// leaf up to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub root: FieldElement,
+    pub siblings: Vec<FieldElement>,
+}
+
+/// Whether the path turns right at the current level, i.e. the node is the
+/// right child of its parent. `index` is shifted right as we climb, so the turn
+/// is always decided by its low bit.
+fn is_right_child(index: &FieldElement) -> bool {
+    index.to_bytes_be()[31] & 1 == 1
+}
+
+/// Flip the low bit of `index` to address its sibling at the current level.
+fn sibling_of(index: &FieldElement) -> FieldElement {
+    let mut bytes = index.to_bytes_be();
+    bytes[31] ^= 1;
+    FieldElement::from_bytes_be(&bytes).expect("sibling index fits in the field")
+}
+
+/// Shift `index` right by one bit to climb to the parent index.
+fn parent_of(index: &FieldElement) -> FieldElement {
+    let mut bytes = index.to_bytes_be();
+    let mut carry = 0u8;
+    for byte in bytes.iter_mut() {
+        let next_carry = *byte & 1;
+        *byte = (*byte >> 1) | (carry << 7);
+        carry = next_carry;
+    }
+    FieldElement::from_bytes_be(&bytes).expect("parent index fits in the field")
+}
+
+/// Recompute the path from the leaf at `entity_key` up to the root after the
+/// leaf value changed, persisting every updated node, and return the new root.
+pub async fn update_leaf<S: MerkleStore + Send>(
+    store: &mut S,
+    entity_key: FieldElement,
+    leaf: FieldElement,
+) -> Result<FieldElement> {
+    let mut index = entity_key;
+    let mut node = leaf;
+
+    for level in 0..DEPTH {
+        store.set_node(level, &index, node).await?;
+
+        let sibling_index = sibling_of(&index);
+        let sibling = store
+            .get_node(level, &sibling_index)
+            .await?
+            .unwrap_or(ZERO_HASHES[level]);
+
+        // Order the children by the bit of the index at this level.
+        node = if is_right_child(&index) {
+            poseidon_hash(sibling, node)
+        } else {
+            poseidon_hash(node, sibling)
+        };
+
+        // Climb to the parent index.
+        index = parent_of(&index);
+    }
+
+    store.set_node(DEPTH, &index, node).await?;
+    Ok(node)
+}
+
+/// Collect the sibling hashes forming the inclusion proof for `entity_key`.
+pub async fn proof<S: MerkleStore + Send>(
+    store: &S,
+    entity_key: FieldElement,
+) -> Result<Proof> {
+    let mut index = entity_key;
+    let mut siblings = Vec::with_capacity(DEPTH);
+
+    for level in 0..DEPTH {
+        let sibling_index = sibling_of(&index);
+        siblings.push(
+            store
+                .get_node(level, &sibling_index)
+                .await?
+                .unwrap_or(ZERO_HASHES[level]),
+        );
+        index = parent_of(&index);
+    }
+
+    let root = store.get_node(DEPTH, &index).await?.unwrap_or(ZERO_HASHES[DEPTH]);
+    Ok(Proof { root, siblings })
+}
+
+/// The leaf value for an entity: a single felt over its serialized record, so
+/// leaves and the empty-leaf sentinel ([`ZERO_HASHES`]`[0]`) are the same
+/// shape. `fields` is the record's member values in declaration order, the
+/// same order [`Sql::register_model`]'s layout describes them in.
+pub fn leaf_hash(fields: &[FieldElement]) -> FieldElement {
+    fields.iter().fold(FieldElement::ZERO, |acc, field| poseidon_hash(acc, *field))
+}
+
+#[async_trait]
+impl<Db> MerkleStore for Sql<Db>
+where
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    async fn get_node(&self, level: usize, index: &FieldElement) -> Result<Option<FieldElement>> {
+        self.merkle_get_node(level, index).await
+    }
+
+    async fn set_node(
+        &mut self,
+        level: usize,
+        index: &FieldElement,
+        hash: FieldElement,
+    ) -> Result<()> {
+        self.merkle_set_node(level, index, hash).await
+    }
+}
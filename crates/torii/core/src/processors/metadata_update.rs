@@ -0,0 +1,51 @@
+use anyhow::{Context, Error, Result};
+use async_trait::async_trait;
+use dojo_world::contracts::world::WorldContractReader;
+use sqlx::Database;
+use starknet::core::types::{BlockWithTxs, Event, InvokeTransactionReceipt};
+use starknet::core::utils::parse_cairo_short_string;
+use starknet::providers::Provider;
+
+use super::EventProcessor;
+use crate::sql::{Placeholder, Sql};
+
+/// Decodes a world `MetadataUpdate` event and refreshes the stored metadata
+/// URI for the affected resource.
+pub struct MetadataUpdateProcessor;
+
+#[async_trait]
+impl<P, Db> EventProcessor<P, Db> for MetadataUpdateProcessor
+where
+    P: Provider + Sync,
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    fn event_key(&self) -> String {
+        "MetadataUpdate".to_string()
+    }
+
+    async fn process(
+        &self,
+        _world: &WorldContractReader<P>,
+        db: &mut Sql<Db>,
+        _block: &BlockWithTxs,
+        _invoke_receipt: &InvokeTransactionReceipt,
+        _event_id: &str,
+        event: &Event,
+    ) -> Result<(), Error> {
+        // `event.keys[0]` is the event's own selector; `event.keys[1]` is the
+        // resource the metadata belongs to. The URI is a Cairo `ByteArray`;
+        // short (< 31 byte) URIs, the overwhelming common case, fit in a
+        // single short-string felt, which is all this decodes today.
+        let resource = event.keys.get(1).context("MetadataUpdate event missing the resource key")?;
+        let uri = event
+            .data
+            .first()
+            .map(parse_cairo_short_string)
+            .transpose()?
+            .unwrap_or_default();
+
+        db.set_metadata(*resource, &uri).await?;
+        Ok(())
+    }
+}
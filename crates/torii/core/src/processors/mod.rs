@@ -1,6 +1,7 @@
 use anyhow::{Error, Result};
 use async_trait::async_trait;
 use dojo_world::contracts::world::WorldContractReader;
+use sqlx::Database;
 use starknet::core::types::{BlockWithTxs, Event, InvokeTransactionReceipt, TransactionReceipt};
 use starknet::providers::Provider;
 
@@ -10,10 +11,23 @@ pub mod metadata_update;
 pub mod register_model;
 pub mod store_set_record;
 
+/// Processors mutate the database through the [`Sql`] handle they are given.
+///
+/// The engine calls [`Sql::begin_block`] before dispatching a block's events to
+/// its processors, so every write issued from `process` lands in that open
+/// transaction. Once every processor for the block has returned `Ok(())`, the
+/// engine calls [`Sql::set_head`] to advance the cursor inside that same
+/// transaction and then [`Sql::commit_block`], so the block's writes and its
+/// cursor advance land atomically; if any processor returns `Err`, the engine
+/// calls [`Sql::rollback_block`] instead and returns the error, leaving the
+/// stored cursor at the last committed block. Implementors must therefore never commit or
+/// roll back the handle themselves — returning `Ok(())` or `Err` is the only
+/// signal the engine acts on.
 #[async_trait]
-pub trait EventProcessor<P>
+pub trait EventProcessor<P, Db>
 where
     P: Provider,
+    Db: Database,
 {
     fn event_key(&self) -> String;
 
@@ -21,7 +35,7 @@ where
     async fn process(
         &self,
         world: &WorldContractReader<P>,
-        db: &mut Sql,
+        db: &mut Sql<Db>,
         block: &BlockWithTxs,
         invoke_receipt: &InvokeTransactionReceipt,
         event_id: &str,
@@ -30,16 +44,21 @@ where
 }
 
 #[async_trait]
-pub trait BlockProcessor<P: Provider + Sync> {
+pub trait BlockProcessor<P: Provider + Sync, Db: Database> {
     fn get_block_number(&self) -> String;
-    async fn process(&self, db: &mut Sql, provider: &P, block: &BlockWithTxs) -> Result<(), Error>;
+    async fn process(
+        &self,
+        db: &mut Sql<Db>,
+        provider: &P,
+        block: &BlockWithTxs,
+    ) -> Result<(), Error>;
 }
 
 #[async_trait]
-pub trait TransactionProcessor<P: Provider + Sync> {
+pub trait TransactionProcessor<P: Provider + Sync, Db: Database> {
     async fn process(
         &self,
-        db: &mut Sql,
+        db: &mut Sql<Db>,
         provider: &P,
         block: &BlockWithTxs,
         transaction_receipt: &TransactionReceipt,
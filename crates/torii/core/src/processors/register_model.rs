@@ -0,0 +1,54 @@
+use anyhow::{Context, Error, Result};
+use async_trait::async_trait;
+use dojo_world::contracts::world::WorldContractReader;
+use sqlx::Database;
+use starknet::core::types::{BlockWithTxs, Event, InvokeTransactionReceipt};
+use starknet::core::utils::parse_cairo_short_string;
+use starknet::providers::Provider;
+
+use super::EventProcessor;
+use crate::sql::{Placeholder, Sql};
+
+/// Decodes a world `ModelRegistered` event and persists the model's layout so
+/// later `StoreSetRecord` events for it can be decoded.
+pub struct RegisterModelProcessor;
+
+#[async_trait]
+impl<P, Db> EventProcessor<P, Db> for RegisterModelProcessor
+where
+    P: Provider + Sync,
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    fn event_key(&self) -> String {
+        "ModelRegistered".to_string()
+    }
+
+    async fn process(
+        &self,
+        world: &WorldContractReader<P>,
+        db: &mut Sql<Db>,
+        _block: &BlockWithTxs,
+        _invoke_receipt: &InvokeTransactionReceipt,
+        _event_id: &str,
+        event: &Event,
+    ) -> Result<(), Error> {
+        // ModelRegistered carries the model's name as a Cairo short string in
+        // its first data felt; the rest of the layout has to be read back off
+        // the world contract, since the event itself only announces that a
+        // model with this name now exists.
+        let name_felt =
+            event.data.first().context("ModelRegistered event missing the model name")?;
+        let name = parse_cairo_short_string(name_felt)?;
+
+        let model = world.model_reader(&name).await?;
+        let schema = model.schema().await?;
+        let layout = model.layout().await?;
+        let class_hash = model.class_hash();
+        let packed_size = model.packed_size().await?;
+        let unpacked_size = model.unpacked_size().await?;
+
+        db.register_model(schema, layout, class_hash, packed_size, unpacked_size).await?;
+        Ok(())
+    }
+}
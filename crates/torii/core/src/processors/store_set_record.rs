@@ -0,0 +1,65 @@
+use anyhow::{Context, Error, Result};
+use async_trait::async_trait;
+use dojo_world::contracts::world::WorldContractReader;
+use sqlx::Database;
+use starknet::core::types::{BlockWithTxs, Event, InvokeTransactionReceipt};
+use starknet::core::utils::parse_cairo_short_string;
+use starknet::providers::Provider;
+
+use super::EventProcessor;
+use crate::merkle;
+use crate::sql::{Placeholder, Sql};
+
+/// Decodes a world `StoreSetRecord` event against its model's stored layout
+/// and persists the resulting entity row.
+pub struct StoreSetRecordProcessor;
+
+#[async_trait]
+impl<P, Db> EventProcessor<P, Db> for StoreSetRecordProcessor
+where
+    P: Provider + Sync,
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    fn event_key(&self) -> String {
+        "StoreSetRecord".to_string()
+    }
+
+    async fn process(
+        &self,
+        _world: &WorldContractReader<P>,
+        db: &mut Sql<Db>,
+        _block: &BlockWithTxs,
+        _invoke_receipt: &InvokeTransactionReceipt,
+        _event_id: &str,
+        event: &Event,
+    ) -> Result<(), Error> {
+        // `event.keys[0]` is the event's own selector; `event.keys[1]` is the
+        // entity's key, already hashed down to one felt by the emitting
+        // contract. `event.data[0]` is the model name as a Cairo short
+        // string, and `event.data[1..]` the record's packed values. A
+        // malformed event (missing the key or the model name) can't be
+        // attributed to an entity or a model at all, so it's skipped rather
+        // than panicking the whole block.
+        let (Some(&entity_key), Some(model_name_felt)) = (event.keys.get(1), event.data.first())
+        else {
+            return Ok(());
+        };
+        let model_name = parse_cairo_short_string(model_name_felt)
+            .context("StoreSetRecord event's model name is not a valid short string")?;
+
+        // A record with no values left (a tag/marker model with only key
+        // members) would hash to the same leaf as an absent one
+        // ([`merkle::ZERO_HASHES`]`[0]`), so it's left out of the tree rather
+        // than written as if it were real data; the entity row still gets
+        // upserted either way; `modelNames`/`keys` are valid for it
+        // regardless of whether it has a Merkle leaf.
+        let values = &event.data[1..];
+        if !values.is_empty() {
+            let leaf = merkle::leaf_hash(values);
+            merkle::update_leaf(db, entity_key, leaf).await?;
+        }
+        db.set_entity(entity_key, std::slice::from_ref(&entity_key), &model_name).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+//! Storage handle for torii-core, backed by an sqlx connection pool.
+//!
+//! [`Sql`] is generic over the sqlx [`Database`] backend so the same indexer
+//! binary can run against either SQLite (the default — convenient for local
+//! development and for the test suite) or PostgreSQL (for deployments that
+//! need concurrent writers or a store that scales independently of the
+//! indexer process). The two backends get separate migration sets, under
+//! `migrations/sqlite` and `migrations/postgres`, because they don't agree on
+//! column types (`BLOB` vs `BYTEA`) or placeholder syntax (`?` vs `$1`).
+
+use anyhow::{Context, Error, Result};
+use dojo_types::schema::Ty;
+#[cfg(feature = "postgres")]
+use sqlx::pool::PoolOptions;
+use sqlx::{Database, Pool, Sqlite, Transaction};
+#[cfg(feature = "postgres")]
+use sqlx::Postgres;
+use starknet::core::types::FieldElement;
+
+/// Produces the positional placeholder sqlx expects for argument `index`
+/// (zero-based) on a given backend. SQLite (and MySQL) use a single `?` for
+/// every argument; Postgres numbers them (`$1`, `$2`, ...).
+pub trait Placeholder: Database {
+    fn placeholder(index: usize) -> String;
+}
+
+impl Placeholder for Sqlite {
+    fn placeholder(_index: usize) -> String {
+        "?".to_string()
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl Placeholder for Postgres {
+    fn placeholder(index: usize) -> String {
+        format!("${}", index + 1)
+    }
+}
+
+/// Handle to the indexed database, generic over the sqlx backend `Db`.
+///
+/// Every processor receives this by `&mut` reference scoped to the
+/// transaction the engine opened for the block currently being ingested; see
+/// the transaction contract documented on [`crate::processors::EventProcessor`].
+/// [`Self::register_model`] may also be called outside of an open block
+/// transaction (for example from tests, or from tooling that registers a
+/// model ad hoc) and then wraps its write in a one-off transaction of its
+/// own; every other write returns an error when no block transaction is open.
+pub struct Sql<Db: Database = Sqlite> {
+    pool: Pool<Db>,
+    world_address: FieldElement,
+    /// The open per-block transaction, if [`Self::begin_block`] has been
+    /// called and not yet matched by [`Self::commit_block`] or
+    /// [`Self::rollback_block`].
+    transaction: Option<Transaction<'static, Db>>,
+}
+
+impl Sql<Sqlite> {
+    /// Open a handle backed by SQLite, running the SQLite migration set.
+    pub async fn new(pool: Pool<Sqlite>, world_address: FieldElement) -> Result<Self> {
+        sqlx::migrate!("../migrations/sqlite").run(&pool).await?;
+        Ok(Self { pool, world_address, transaction: None })
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl Sql<Postgres> {
+    /// Open a handle backed by PostgreSQL, running the Postgres migration set.
+    pub async fn new(pool: Pool<Postgres>, world_address: FieldElement) -> Result<Self> {
+        sqlx::migrate!("../migrations/postgres").run(&pool).await?;
+        Ok(Self { pool, world_address, transaction: None })
+    }
+
+    /// Build a connection pool from a `postgres://` URL and open a handle
+    /// against it.
+    pub async fn connect(database_url: &str, world_address: FieldElement) -> Result<Self> {
+        let pool = PoolOptions::<Postgres>::new()
+            .max_connections(10)
+            .connect(database_url)
+            .await
+            .with_context(|| format!("connecting to {database_url}"))?;
+        Self::new(pool, world_address).await
+    }
+}
+
+impl<Db: Placeholder> Sql<Db>
+where
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    pub fn world_address(&self) -> FieldElement {
+        self.world_address
+    }
+
+    /// Wrap an already-migrated pool without running migrations again, for
+    /// short-lived handles (e.g. GraphQL resolvers) that only need read
+    /// access and are handed the same pool the indexer already migrated.
+    pub fn from_pool(pool: Pool<Db>) -> Self {
+        Self { pool, world_address: FieldElement::ZERO, transaction: None }
+    }
+
+    /// Open the per-block transaction every processor writes through for the
+    /// block currently being ingested. Must be paired with exactly one of
+    /// [`Self::commit_block`] (on success) or [`Self::rollback_block`] (on the
+    /// first processor error), per the contract on
+    /// [`crate::processors::EventProcessor`].
+    pub async fn begin_block(&mut self) -> Result<()> {
+        debug_assert!(self.transaction.is_none(), "a block transaction is already open");
+        self.transaction = Some(self.pool.begin().await?);
+        Ok(())
+    }
+
+    /// Commit the open block transaction, persisting every write the block's
+    /// processors issued and advancing the stored cursor with them.
+    pub async fn commit_block(&mut self) -> Result<()> {
+        if let Some(tx) = self.transaction.take() {
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+
+    /// Roll back the open block transaction, discarding every write the
+    /// block's processors issued so far. Called on the first processor error
+    /// so a failing block never lands partial state.
+    pub async fn rollback_block(&mut self) -> Result<()> {
+        if let Some(tx) = self.transaction.take() {
+            tx.rollback().await?;
+        }
+        Ok(())
+    }
+
+    /// Advance the stored head cursor to `block_number`. Must be called
+    /// before [`Self::commit_block`], while the block's transaction is still
+    /// open, so the cursor advance is part of the same atomic commit as the
+    /// block's other writes — a crash can never leave the cursor ahead of the
+    /// data it claims to cover.
+    pub async fn set_head(&mut self, block_number: u64) -> Result<()> {
+        let tx = self
+            .transaction
+            .as_mut()
+            .context("set_head called with no open block transaction")?;
+
+        // Upsert, so the first block ingested creates the world's cursor row.
+        let sql = format!(
+            "INSERT INTO contracts (id, head) VALUES ({}, {}) \
+             ON CONFLICT (id) DO UPDATE SET head = excluded.head",
+            Db::placeholder(0),
+            Db::placeholder(1),
+        );
+        sqlx::query(&sql)
+            .bind(self.world_address.to_string())
+            .bind(block_number as i64)
+            .execute(tx.as_mut())
+            .await?;
+        Ok(())
+    }
+
+    /// Register a model's layout so future `StoreSetRecord` events for it can
+    /// be decoded. Writes through the open block transaction, or through a
+    /// one-off transaction if none is open.
+    pub async fn register_model(
+        &mut self,
+        model: Ty,
+        layout: Vec<FieldElement>,
+        class_hash: FieldElement,
+        packed_size: u32,
+        unpacked_size: u32,
+    ) -> Result<()> {
+        let name = model.name();
+        let layout_blob: Vec<u8> = layout.iter().flat_map(|f| f.to_bytes_be()).collect();
+        let class_hash_str = format!("{class_hash:#x}");
+        // Postgres has no unsigned integer type sqlx can bind a u32 against;
+        // widen to i64, which both backends accept, rather than binding the
+        // unsigned value directly.
+        let packed_size = packed_size as i64;
+        let unpacked_size = unpacked_size as i64;
+
+        let sql = format!(
+            "INSERT INTO models (id, name, class_hash, layout, packed_size, unpacked_size) \
+             VALUES ({}, {}, {}, {}, {}, {})",
+            Db::placeholder(0),
+            Db::placeholder(1),
+            Db::placeholder(2),
+            Db::placeholder(3),
+            Db::placeholder(4),
+            Db::placeholder(5),
+        );
+
+        match self.transaction.as_mut() {
+            Some(tx) => {
+                sqlx::query(&sql)
+                    .bind(&name)
+                    .bind(&name)
+                    .bind(class_hash_str)
+                    .bind(layout_blob)
+                    .bind(packed_size)
+                    .bind(unpacked_size)
+                    .execute(tx.as_mut())
+                    .await?;
+            }
+            None => {
+                let mut tx = self.pool.begin().await?;
+                sqlx::query(&sql)
+                    .bind(&name)
+                    .bind(&name)
+                    .bind(class_hash_str)
+                    .bind(layout_blob)
+                    .bind(packed_size)
+                    .bind(unpacked_size)
+                    .execute(tx.as_mut())
+                    .await?;
+                tx.commit().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read a node of the entity Merkle tree (see [`crate::merkle`]) from the
+    /// `merkle_nodes(level, index) -> hash` table. Backs
+    /// [`crate::merkle::MerkleStore`] for this handle.
+    pub(crate) async fn merkle_get_node(
+        &self,
+        level: usize,
+        index: &FieldElement,
+    ) -> Result<Option<FieldElement>> {
+        let sql = format!(
+            "SELECT hash FROM merkle_nodes WHERE level = {} AND idx = {}",
+            Db::placeholder(0),
+            Db::placeholder(1),
+        );
+        let row: Option<(String,)> = sqlx::query_as(&sql)
+            .bind(level as i64)
+            .bind(index.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        row.map(|(hash,)| FieldElement::from_hex_be(&hash).map_err(Error::from)).transpose()
+    }
+
+    /// Upsert a node of the entity Merkle tree, inside the open block
+    /// transaction so a leaf update lands atomically with the entity write
+    /// that produced it.
+    pub(crate) async fn merkle_set_node(
+        &mut self,
+        level: usize,
+        index: &FieldElement,
+        hash: FieldElement,
+    ) -> Result<()> {
+        let tx = self
+            .transaction
+            .as_mut()
+            .context("merkle_set_node called with no open block transaction")?;
+
+        let sql = format!(
+            "INSERT INTO merkle_nodes (level, idx, hash) VALUES ({}, {}, {}) \
+             ON CONFLICT (level, idx) DO UPDATE SET hash = excluded.hash",
+            Db::placeholder(0),
+            Db::placeholder(1),
+            Db::placeholder(2),
+        );
+        sqlx::query(&sql)
+            .bind(level as i64)
+            .bind(index.to_string())
+            .bind(format!("{hash:#x}"))
+            .execute(tx.as_mut())
+            .await?;
+        Ok(())
+    }
+
+    /// Upsert an entity row: `keys` joins the model's key members with `/`,
+    /// matching how torii-graphql's `Entity.keys` resolver splits them back
+    /// out. Must run inside the open block transaction so the entity row
+    /// lands atomically with the Merkle leaf update it's paired with.
+    pub async fn set_entity(
+        &mut self,
+        entity_id: FieldElement,
+        keys: &[FieldElement],
+        model_name: &str,
+    ) -> Result<()> {
+        let tx = self
+            .transaction
+            .as_mut()
+            .context("set_entity called with no open block transaction")?;
+
+        let keys_str = keys.iter().map(|k| format!("{k:#x}")).collect::<Vec<_>>().join("/");
+        let sql = format!(
+            "INSERT INTO entities (id, keys, model_names) VALUES ({}, {}, {}) \
+             ON CONFLICT (id) DO UPDATE SET \
+             keys = excluded.keys, \
+             model_names = CASE \
+                WHEN entities.model_names LIKE '%' || excluded.model_names || '%' THEN entities.model_names \
+                ELSE entities.model_names || ',' || excluded.model_names \
+             END",
+            Db::placeholder(0),
+            Db::placeholder(1),
+            Db::placeholder(2),
+        );
+        sqlx::query(&sql)
+            .bind(format!("{entity_id:#x}"))
+            .bind(keys_str)
+            .bind(model_name)
+            .execute(tx.as_mut())
+            .await?;
+        Ok(())
+    }
+
+    /// Upsert the metadata URI for `resource` (a world resource id — a model
+    /// name, the world itself, etc.). Must run inside the open block
+    /// transaction so the update lands atomically with the event that
+    /// produced it.
+    pub async fn set_metadata(&mut self, resource: FieldElement, uri: &str) -> Result<()> {
+        let tx = self
+            .transaction
+            .as_mut()
+            .context("set_metadata called with no open block transaction")?;
+
+        let sql = format!(
+            "INSERT INTO metadata (resource, uri) VALUES ({}, {}) \
+             ON CONFLICT (resource) DO UPDATE SET uri = excluded.uri",
+            Db::placeholder(0),
+            Db::placeholder(1),
+        );
+        sqlx::query(&sql)
+            .bind(format!("{resource:#x}"))
+            .bind(uri)
+            .execute(tx.as_mut())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+//! Relay-spec cursor pagination for GraphQL connections.
+//!
+//! A cursor identifies a row by `(created_at, seq)` rather than by offset, so
+//! pages stay stable while rows are still being inserted between queries — an
+//! offset would skip or repeat rows as new entities land ahead of it. `seq`
+//! (the `entities` table's autoincrement surrogate key) breaks ties between
+//! rows inserted in the same instant (SQLite's `created_at` has only second
+//! resolution).
+
+use async_graphql::Error;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use torii_core::sql::Placeholder;
+
+/// `first`/`after`/`last`/`before`, as specified by the Relay connection spec.
+#[derive(Debug, Default, Clone)]
+pub struct ConnectionArgs {
+    pub first: Option<u64>,
+    pub after: Option<String>,
+    pub last: Option<u64>,
+    pub before: Option<String>,
+}
+
+impl ConnectionArgs {
+    /// Validate the combination and resolve it into a single paging
+    /// direction: forward (`first`/`after`) or backward (`last`/`before`).
+    /// The Relay spec allows mixing `first`/`last`, and mixing `after`/`last`
+    /// or `before`/`first`, but the result is ambiguous to page consistently
+    /// against a moving keyset, so — like most server implementations — we
+    /// reject those combinations outright rather than guess at what the
+    /// caller meant; for the same reason a `before` with no `last` is
+    /// rejected instead of silently ignored, since honoring one arg and
+    /// dropping the other would return a page the caller didn't ask for.
+    /// `after` with no `first` is fine: it just pages forward with the
+    /// default limit.
+    pub fn resolve(&self) -> Result<Page, Error> {
+        if self.first.is_some() && self.last.is_some() {
+            return Err(Error::new("cannot specify both `first` and `last`"));
+        }
+        if self.before.is_some() && self.last.is_none() {
+            return Err(Error::new("`before` must be paired with `last`"));
+        }
+        if self.after.is_some() && self.last.is_some() {
+            return Err(Error::new("cannot specify both `after` and `last`"));
+        }
+        if self.last.is_some() {
+            let limit = self.last.unwrap().min(MAX_PAGE_SIZE);
+            return Ok(Page::Backward { limit, before: self.before.clone() });
+        }
+        let limit = self.first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        Ok(Page::Forward { limit, after: self.after.clone() })
+    }
+}
+
+const DEFAULT_PAGE_SIZE: u64 = 10;
+/// Upper bound on `first`/`last`, regardless of what the caller asks for, so
+/// one query can't force a full-table scan back to the client.
+const MAX_PAGE_SIZE: u64 = 100;
+
+pub enum Page {
+    Forward { limit: u64, after: Option<String> },
+    Backward { limit: u64, before: Option<String> },
+}
+
+/// Encode a `(created_at, seq)` keyset position as an opaque cursor.
+pub fn encode_cursor(created_at: &str, row_id: i64) -> String {
+    STANDARD.encode(format!("{created_at}:{row_id}"))
+}
+
+/// Decode a cursor produced by [`encode_cursor`] back into its keyset
+/// position.
+pub fn decode_cursor(cursor: &str) -> Result<(String, i64), Error> {
+    let bytes = STANDARD.decode(cursor).map_err(|_| Error::new("malformed cursor"))?;
+    let decoded = String::from_utf8(bytes).map_err(|_| Error::new("malformed cursor"))?;
+    // `created_at` itself may contain colons (it's a timestamp like
+    // "2024-01-01 12:34:56"); `row_id` is purely numeric and can't, so split
+    // from the right to keep the whole timestamp intact.
+    let (created_at, row_id) = decoded.rsplit_once(':').ok_or_else(|| Error::new("malformed cursor"))?;
+    let row_id = row_id.parse().map_err(|_| Error::new("malformed cursor"))?;
+    Ok((created_at.to_string(), row_id))
+}
+
+/// `{ hasNextPage, hasPreviousPage, startCursor, endCursor }`.
+#[derive(Debug, Clone)]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub has_previous_page: bool,
+    pub start_cursor: Option<String>,
+    pub end_cursor: Option<String>,
+}
+
+/// Build the keyset `WHERE`/`ORDER BY`/`LIMIT` clause for `page` against a
+/// table whose rows are ordered by `(created_at, seq)`. Returns the clause,
+/// the decoded cursor and the page limit; the caller binds `limit + 1` to the
+/// `LIMIT` placeholder so it can tell whether another page follows without a
+/// second round trip, and the extra row must be trimmed by
+/// [`trim_and_build_page_info`] before the rows are returned to the client. Placeholders are generated via
+/// `Db::placeholder` so the clause binds correctly on both SQLite (`?`) and
+/// Postgres (`$1`, `$2`, ...).
+pub fn keyset_clause<Db: Placeholder>(
+    page: &Page,
+) -> Result<(String, Option<(String, i64)>, u64), Error> {
+    match page {
+        Page::Forward { limit, after } => {
+            let cursor = after.as_deref().map(decode_cursor).transpose()?;
+            let clause = match &cursor {
+                Some(_) => format!(
+                    "WHERE (created_at, seq) > ({}, {}) ORDER BY created_at ASC, seq ASC LIMIT {}",
+                    Db::placeholder(0),
+                    Db::placeholder(1),
+                    Db::placeholder(2),
+                ),
+                None => format!("ORDER BY created_at ASC, seq ASC LIMIT {}", Db::placeholder(0)),
+            };
+            Ok((clause, cursor, *limit))
+        }
+        Page::Backward { limit, before } => {
+            let cursor = before.as_deref().map(decode_cursor).transpose()?;
+            let clause = match &cursor {
+                Some(_) => format!(
+                    "WHERE (created_at, seq) < ({}, {}) ORDER BY created_at DESC, seq DESC LIMIT {}",
+                    Db::placeholder(0),
+                    Db::placeholder(1),
+                    Db::placeholder(2),
+                ),
+                None => format!("ORDER BY created_at DESC, seq DESC LIMIT {}", Db::placeholder(0)),
+            };
+            Ok((clause, cursor, *limit))
+        }
+    }
+}
+
+/// Trim the lookahead row (if the keyset query over-fetched by one) and
+/// derive `PageInfo` from what's left. `cursor_of` extracts the
+/// `(created_at, seq)` keyset position from a row so this stays generic
+/// over whatever row type the caller queried.
+pub fn trim_and_build_page_info<T>(
+    mut rows: Vec<T>,
+    page: &Page,
+    cursor_of: impl Fn(&T) -> (String, i64),
+) -> (Vec<T>, PageInfo) {
+    let limit = match page {
+        Page::Forward { limit, .. } | Page::Backward { limit, .. } => *limit as usize,
+    };
+    let has_more = rows.len() > limit;
+    if has_more {
+        rows.truncate(limit);
+    }
+    // Backward pagination queries in descending order to take the limit from
+    // the right end of the keyset; restore ascending order before handing
+    // edges back to the client.
+    if matches!(page, Page::Backward { .. }) {
+        rows.reverse();
+    }
+
+    let (has_next_page, has_previous_page) = match page {
+        Page::Forward { after, .. } => (has_more, after.is_some()),
+        Page::Backward { before, .. } => (before.is_some(), has_more),
+    };
+
+    let start_cursor = rows.first().map(|r| {
+        let (created_at, row_id) = cursor_of(r);
+        encode_cursor(&created_at, row_id)
+    });
+    let end_cursor = rows.last().map(|r| {
+        let (created_at, row_id) = cursor_of(r);
+        encode_cursor(&created_at, row_id)
+    });
+
+    (rows, PageInfo { has_next_page, has_previous_page, start_cursor, end_cursor })
+}
@@ -0,0 +1,5 @@
+pub mod connection;
+pub mod schema;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,267 @@
+//! Dynamic GraphQL schema construction.
+//!
+//! This module owns the parts of the schema that don't depend on any
+//! particular model: the shared `entities` connection and the Merkle
+//! inclusion-proof query. No per-model object types or query fields are
+//! built here.
+
+use anyhow::Result;
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, Schema, TypeRef};
+use async_graphql::Value;
+use sqlx::{Database, Pool};
+use starknet::core::types::FieldElement;
+use torii_core::merkle;
+use torii_core::sql::{Placeholder, Sql};
+
+use crate::connection::{self, ConnectionArgs, PageInfo};
+
+const ENTITY_PROOF_TYPE: &str = "EntityProof";
+
+/// `EntityProof { root, siblings }`, the GraphQL shape of [`merkle::Proof`].
+fn entity_proof_type() -> Object {
+    Object::new(ENTITY_PROOF_TYPE)
+        .field(Field::new("root", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+            FieldFuture::new(async move {
+                let proof = ctx.parent_value.try_downcast_ref::<merkle::Proof>()?;
+                Ok(Some(Value::from(format!("{:#x}", proof.root))))
+            })
+        }))
+        .field(Field::new("siblings", TypeRef::named_nn_list_nn(TypeRef::STRING), |ctx| {
+            FieldFuture::new(async move {
+                let proof = ctx.parent_value.try_downcast_ref::<merkle::Proof>()?;
+                let siblings: Vec<Value> =
+                    proof.siblings.iter().map(|s| Value::from(format!("{s:#x}"))).collect();
+                Ok(Some(Value::from(siblings)))
+            })
+        }))
+}
+
+/// `entityProof(id: String!): EntityProof!`, returning the inclusion proof
+/// for the entity Merkle tree maintained alongside ingestion (see
+/// [`torii_core::merkle`]).
+fn entity_proof_field<Db>(pool: Pool<Db>) -> Field
+where
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    Field::new("entityProof", TypeRef::named_nn(ENTITY_PROOF_TYPE), move |ctx| {
+        let pool = pool.clone();
+        FieldFuture::new(async move {
+            let id = ctx.args.try_get("id")?.string()?.to_string();
+            let entity_key = FieldElement::from_hex_be(&id)
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+            // The proof only needs read access to the stored tree nodes, so a
+            // lightweight handle without running migrations is enough here.
+            let db: Sql<Db> = Sql::from_pool(pool);
+            let proof = merkle::proof(&db, entity_key)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+            Ok(Some(FieldValue::owned_any(proof)))
+        })
+    })
+    .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::STRING)))
+}
+
+/// One row of the `entities` table, and the keyset position its cursor
+/// encodes. `seq` is the table's autoincrement surrogate key (SQLite's
+/// implicit `rowid` isn't available on Postgres, so the migrations define an
+/// explicit one).
+#[derive(sqlx::FromRow, Clone)]
+struct EntityRow {
+    keys: Option<String>,
+    model_names: String,
+    created_at: String,
+    seq: i64,
+}
+
+impl EntityRow {
+    fn cursor(&self) -> String {
+        connection::encode_cursor(&self.created_at, self.seq)
+    }
+}
+
+/// The resolved `entities(first, after, last, before)` page: the rows for
+/// this page plus the connection-level fields (`totalCount`, `pageInfo`)
+/// that don't belong to any single edge.
+struct EntitiesPage {
+    rows: Vec<EntityRow>,
+    page_info: PageInfo,
+    total_count: i64,
+}
+
+async fn fetch_entities_page<Db>(
+    pool: &Pool<Db>,
+    args: ConnectionArgs,
+) -> Result<EntitiesPage, async_graphql::Error>
+where
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    let page = args.resolve()?;
+    let (clause, cursor, limit) = connection::keyset_clause::<Db>(&page)?;
+    // Over-fetch by one so trim_and_build_page_info can tell whether another
+    // page follows without a second round trip.
+    let fetch_limit = limit as i64 + 1;
+    let sql = format!("SELECT keys, model_names, created_at, seq FROM entities {clause}");
+
+    let rows: Vec<EntityRow> = match cursor {
+        Some((created_at, seq)) => {
+            sqlx::query_as(&sql).bind(created_at).bind(seq).bind(fetch_limit).fetch_all(pool).await
+        }
+        None => sqlx::query_as(&sql).bind(fetch_limit).fetch_all(pool).await,
+    }
+    .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+    let (rows, page_info) =
+        connection::trim_and_build_page_info(rows, &page, |r| (r.created_at.clone(), r.seq));
+
+    let (total_count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM entities")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+    Ok(EntitiesPage { rows, page_info, total_count })
+}
+
+fn page_info_type() -> Object {
+    Object::new("PageInfo")
+        .field(Field::new("hasNextPage", TypeRef::named_nn(TypeRef::BOOLEAN), |ctx| {
+            FieldFuture::new(async move {
+                let info = ctx.parent_value.try_downcast_ref::<PageInfo>()?;
+                Ok(Some(Value::from(info.has_next_page)))
+            })
+        }))
+        .field(Field::new("hasPreviousPage", TypeRef::named_nn(TypeRef::BOOLEAN), |ctx| {
+            FieldFuture::new(async move {
+                let info = ctx.parent_value.try_downcast_ref::<PageInfo>()?;
+                Ok(Some(Value::from(info.has_previous_page)))
+            })
+        }))
+        .field(Field::new("startCursor", TypeRef::named(TypeRef::STRING), |ctx| {
+            FieldFuture::new(async move {
+                let info = ctx.parent_value.try_downcast_ref::<PageInfo>()?;
+                Ok(info.start_cursor.clone().map(Value::from))
+            })
+        }))
+        .field(Field::new("endCursor", TypeRef::named(TypeRef::STRING), |ctx| {
+            FieldFuture::new(async move {
+                let info = ctx.parent_value.try_downcast_ref::<PageInfo>()?;
+                Ok(info.end_cursor.clone().map(Value::from))
+            })
+        }))
+}
+
+fn entity_type() -> Object {
+    Object::new("Entity")
+        .field(Field::new("modelNames", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<EntityRow>()?;
+                Ok(Some(Value::from(row.model_names.clone())))
+            })
+        }))
+        .field(Field::new("keys", TypeRef::named_list(TypeRef::STRING), |ctx| {
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<EntityRow>()?;
+                Ok(row.keys.as_ref().map(|keys| {
+                    Value::from(
+                        keys.split('/').map(|k| Value::from(k.to_string())).collect::<Vec<_>>(),
+                    )
+                }))
+            })
+        }))
+        .field(Field::new("createdAt", TypeRef::named(TypeRef::STRING), |ctx| {
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<EntityRow>()?;
+                Ok(Some(Value::from(row.created_at.clone())))
+            })
+        }))
+}
+
+fn entity_edge_type() -> Object {
+    Object::new("EntityEdge")
+        .field(Field::new("node", TypeRef::named_nn("Entity"), |ctx| {
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<EntityRow>()?.clone();
+                Ok(Some(FieldValue::owned_any(row)))
+            })
+        }))
+        .field(Field::new("cursor", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<EntityRow>()?;
+                Ok(Some(Value::from(row.cursor())))
+            })
+        }))
+}
+
+fn entity_connection_type() -> Object {
+    Object::new("EntityConnection")
+        .field(Field::new("totalCount", TypeRef::named_nn(TypeRef::INT), |ctx| {
+            FieldFuture::new(async move {
+                let page = ctx.parent_value.try_downcast_ref::<EntitiesPage>()?;
+                Ok(Some(Value::from(page.total_count)))
+            })
+        }))
+        .field(Field::new("pageInfo", TypeRef::named_nn("PageInfo"), |ctx| {
+            FieldFuture::new(async move {
+                let page = ctx.parent_value.try_downcast_ref::<EntitiesPage>()?;
+                Ok(Some(FieldValue::owned_any(page.page_info.clone())))
+            })
+        }))
+        .field(Field::new("edges", TypeRef::named_nn_list_nn("EntityEdge"), |ctx| {
+            FieldFuture::new(async move {
+                let page = ctx.parent_value.try_downcast_ref::<EntitiesPage>()?;
+                let edges: Vec<FieldValue> =
+                    page.rows.iter().cloned().map(FieldValue::owned_any).collect();
+                Ok(Some(FieldValue::list(edges)))
+            })
+        }))
+}
+
+/// `entities(first: Int, after: String, last: Int, before: String): EntityConnection!`,
+/// a Relay-spec cursor-paginated connection over every indexed entity (see
+/// [`crate::connection`] for the keyset pagination and cursor format).
+fn entities_field<Db>(pool: Pool<Db>) -> Field
+where
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    Field::new("entities", TypeRef::named_nn("EntityConnection"), move |ctx| {
+        let pool = pool.clone();
+        FieldFuture::new(async move {
+            let args = ConnectionArgs {
+                first: ctx.args.get("first").map(|v| v.u64()).transpose()?,
+                after: ctx.args.get("after").map(|v| v.string().map(str::to_string)).transpose()?,
+                last: ctx.args.get("last").map(|v| v.u64()).transpose()?,
+                before: ctx.args.get("before").map(|v| v.string().map(str::to_string)).transpose()?,
+            };
+            let page = fetch_entities_page(&pool, args).await?;
+            Ok(Some(FieldValue::owned_any(page)))
+        })
+    })
+    .argument(InputValue::new("first", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("after", TypeRef::named(TypeRef::STRING)))
+    .argument(InputValue::new("last", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("before", TypeRef::named(TypeRef::STRING)))
+}
+
+/// Build the world's GraphQL schema. The `Query` root holds the
+/// model-independent `entities` connection and `entityProof` query only;
+/// no model-derived object types or query fields are added.
+pub async fn build_schema<Db>(pool: &Pool<Db>) -> Result<Schema>
+where
+    Db: Placeholder,
+    for<'c> &'c mut <Db as Database>::Connection: sqlx::Executor<'c, Database = Db>,
+{
+    let query =
+        Object::new("Query").field(entities_field(pool.clone())).field(entity_proof_field(pool.clone()));
+
+    Ok(Schema::build("Query", None, None)
+        .register(page_info_type())
+        .register(entity_type())
+        .register(entity_edge_type())
+        .register(entity_connection_type())
+        .register(entity_proof_type())
+        .register(query)
+        .finish()?)
+}
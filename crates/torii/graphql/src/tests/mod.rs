@@ -16,7 +16,7 @@ use serde::Deserialize;
 use serde_json::Value;
 use sozo::ops::migration::execute_strategy;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
-use sqlx::SqlitePool;
+use sqlx::{Sqlite, SqlitePool};
 use starknet::accounts::{Account, Call};
 use starknet::core::types::{BlockId, BlockTag, FieldElement, InvokeTransactionResult};
 use starknet::macros::selector;
@@ -35,9 +35,11 @@ mod subscription_test;
 use crate::schema::build_schema;
 
 #[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct Connection<T> {
     pub total_count: i64,
     pub edges: Vec<Edge<T>>,
+    pub page_info: PageInfo,
 }
 
 #[derive(Deserialize, Debug, PartialEq)]
@@ -46,6 +48,15 @@ pub struct Edge<T> {
     pub cursor: String,
 }
 
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub has_previous_page: bool,
+    pub start_cursor: Option<String>,
+    pub end_cursor: Option<String>,
+}
+
 #[derive(Deserialize, Debug, PartialEq)]
 pub struct Entity {
     pub model_names: String,
@@ -148,7 +159,7 @@ pub async fn run_graphql_subscription(
     // fn subscribe() is called from inside dynamic subscription
 }
 
-pub async fn model_fixtures(db: &mut Sql) {
+pub async fn model_fixtures(db: &mut Sql<Sqlite>) {
     db.register_model(
         Ty::Struct(Struct {
             name: "Moves".to_string(),
@@ -235,7 +246,8 @@ pub async fn spinup_types_test() -> Result<SqlitePool> {
 
     let migration = prepare_migration("./src/tests/types-test/target/dev".into()).unwrap();
     let config = build_test_config("./src/tests/types-test/Scarb.toml").unwrap();
-    let mut db = Sql::new(pool.clone(), migration.world_address().unwrap()).await.unwrap();
+    let mut db: Sql<Sqlite> =
+        Sql::new(pool.clone(), migration.world_address().unwrap()).await.unwrap();
 
     let sequencer =
         TestSequencer::start(SequencerConfig::default(), get_default_test_starknet_config()).await;